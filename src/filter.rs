@@ -0,0 +1,75 @@
+use osmpbfreader::Tags;
+
+/// Anything a [`Filter`] can read tags from: OSM objects or parsed diff records.
+pub trait TagSource {
+    fn lookup(&self, key: &str) -> Option<&str>;
+}
+
+impl TagSource for Tags {
+    fn lookup(&self, key: &str) -> Option<&str> {
+        self.get(key).map(|s| s.as_str())
+    }
+}
+
+impl TagSource for [(String, String)] {
+    fn lookup(&self, key: &str) -> Option<&str> {
+        self.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Which OSM features to extract and which of their tags to keep.
+///
+/// A feature qualifies when it carries *any* of the configured key/value pairs;
+/// a value of `*` matches the key regardless of its value. The city wall profile
+/// is the default, but `--filter key=value` overrides it so the tool can extract
+/// arbitrary matching ways (e.g. `historic=citywalls`, `barrier=wall`, `waterway=*`).
+pub struct Filter {
+    matches: Vec<(String, String)>,
+    default: bool,
+    /// Tag keys copied into output columns/properties beyond `name`.
+    pub copy_tags: Vec<String>,
+}
+
+impl Filter {
+    /// The default profile: historic city walls.
+    pub fn city_walls() -> Self {
+        Self {
+            matches: vec![
+                ("barrier".to_string(), "city_wall".to_string()),
+                ("historic".to_string(), "citywalls".to_string()),
+            ],
+            default: true,
+            copy_tags: Vec::new(),
+        }
+    }
+
+    /// Whether this is still the untouched default profile.
+    pub fn is_default(&self) -> bool {
+        self.default
+    }
+
+    /// Drop the configured matches so custom ones can replace them.
+    pub fn clear_matches(&mut self) {
+        self.matches.clear();
+        self.default = false;
+    }
+
+    /// Add a qualifying `key=value` pair (with `*` as a value wildcard).
+    pub fn add_match(&mut self, key: String, value: String) {
+        self.matches.push((key, value));
+        self.default = false;
+    }
+
+    /// Does the feature carry at least one qualifying tag?
+    pub fn matches<T: TagSource + ?Sized>(&self, tags: &T) -> bool {
+        self.matches.iter().any(|(k, v)| match tags.lookup(k) {
+            Some(found) => v == "*" || found == v,
+            None => false,
+        })
+    }
+
+    /// The values of the configured `copy_tags`, aligned with their order.
+    pub fn extra_values<'a, T: TagSource + ?Sized>(&self, tags: &'a T) -> Vec<Option<&'a str>> {
+        self.copy_tags.iter().map(|k| tags.lookup(k)).collect()
+    }
+}
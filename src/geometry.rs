@@ -0,0 +1,58 @@
+use serde_json::{json, Value};
+
+/// A wall geometry, ready to be handed to a [`WallSink`](crate::sink::WallSink).
+pub enum Geometry {
+    LineString(Vec<(f64, f64)>),
+    MultiLineString(Vec<Vec<(f64, f64)>>),
+    Polygon(Vec<(f64, f64)>),
+}
+
+/// Format a coordinate list as the body of a WKT geometry.
+fn wkt_coords(coords: &[(f64, f64)]) -> String {
+    coords
+        .iter()
+        .map(|(lon, lat)| format!("{lon:.7} {lat:.7}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Turn a coordinate list into a GeoJSON `[lon, lat]` array.
+fn json_coords(coords: &[(f64, f64)]) -> Value {
+    Value::Array(coords.iter().map(|(lon, lat)| json!([lon, lat])).collect())
+}
+
+impl Geometry {
+    /// Render the geometry as WKT for `ST_GeomFromText`.
+    pub fn to_wkt(&self) -> String {
+        match self {
+            Geometry::LineString(c) => format!("LineString({})", wkt_coords(c)),
+            Geometry::MultiLineString(parts) => {
+                let parts = parts
+                    .iter()
+                    .map(|c| format!("({})", wkt_coords(c)))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                format!("MultiLineString({parts})")
+            }
+            Geometry::Polygon(c) => format!("Polygon(({}))", wkt_coords(c)),
+        }
+    }
+
+    /// Render the geometry as a GeoJSON geometry object.
+    pub fn to_geojson(&self) -> Value {
+        match self {
+            Geometry::LineString(c) => json!({
+                "type": "LineString",
+                "coordinates": json_coords(c),
+            }),
+            Geometry::MultiLineString(parts) => json!({
+                "type": "MultiLineString",
+                "coordinates": Value::Array(parts.iter().map(|c| json_coords(c)).collect()),
+            }),
+            Geometry::Polygon(c) => json!({
+                "type": "Polygon",
+                "coordinates": json!([json_coords(c)]),
+            }),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use osmpbfreader::NodeId;
+
+/// OSM stores coordinates as fixed-point integers scaled by 1e7.
+const SCALE: f64 = 1e7;
+
+/// A lookup table from node ID to coordinate, filled in two stages: first the
+/// needed IDs are reserved, then their coordinates are set during the node scan.
+pub trait NodeStore {
+    /// Register a node ID whose coordinate we will need.
+    fn reserve(&mut self, id: NodeId);
+
+    /// Freeze the set of reserved IDs and allocate coordinate storage.
+    fn finalize(&mut self);
+
+    /// Record a coordinate; returns `true` if the ID was actually wanted.
+    fn set(&mut self, id: NodeId, lon: f64, lat: f64) -> bool;
+
+    /// Look up a coordinate, or `None` if unknown or not yet filled.
+    fn get(&self, id: NodeId) -> Option<(f64, f64)>;
+}
+
+/// The classic hash-map backed store: simple, but memory hungry.
+#[derive(Default)]
+pub struct HashStore(HashMap<NodeId, Option<(f64, f64)>>);
+
+impl NodeStore for HashStore {
+    fn reserve(&mut self, id: NodeId) {
+        self.0.entry(id).or_insert(None);
+    }
+
+    fn finalize(&mut self) {}
+
+    fn set(&mut self, id: NodeId, lon: f64, lat: f64) -> bool {
+        if let Entry::Occupied(mut entry) = self.0.entry(id) {
+            entry.insert(Some((lon, lat)));
+            true
+        } else {
+            false
+        }
+    }
+
+    fn get(&self, id: NodeId) -> Option<(f64, f64)> {
+        self.0.get(&id).copied().flatten()
+    }
+}
+
+/// A dense store: a sorted flat array of `(id, lon_i32, lat_i32)` records with
+/// coordinates quantized to fixed point, queried by binary search. This roughly
+/// halves per-node memory versus [`HashStore`] and scales to planet extracts —
+/// the same structure osm2pgsql uses for its middle layer.
+///
+/// The win is footprint, not I/O: the importer still makes the same `.pbf`
+/// passes (reserve ids, then scan nodes) regardless of the store, so this does
+/// not turn the run single-pass — it only lets the node set fit in memory.
+#[derive(Default)]
+pub struct ArrayStore {
+    ids: Vec<i64>,
+    coords: Vec<(i32, i32)>,
+}
+
+/// Sentinel marking a reserved-but-not-yet-filled coordinate.
+const UNSET: i32 = i32::MIN;
+
+impl ArrayStore {
+    fn index(&self, id: NodeId) -> Option<usize> {
+        self.ids.binary_search(&id.0).ok()
+    }
+}
+
+impl NodeStore for ArrayStore {
+    fn reserve(&mut self, id: NodeId) {
+        self.ids.push(id.0);
+    }
+
+    fn finalize(&mut self) {
+        self.ids.sort_unstable();
+        self.ids.dedup();
+        self.coords = vec![(UNSET, UNSET); self.ids.len()];
+    }
+
+    fn set(&mut self, id: NodeId, lon: f64, lat: f64) -> bool {
+        match self.index(id) {
+            Some(i) => {
+                self.coords[i] = ((lon * SCALE).round() as i32, (lat * SCALE).round() as i32);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn get(&self, id: NodeId) -> Option<(f64, f64)> {
+        let (lon, lat) = self.coords[self.index(id)?];
+
+        if lon == UNSET {
+            None
+        } else {
+            Some((lon as f64 / SCALE, lat as f64 / SCALE))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn array_store_reserves_sets_and_gets() {
+        let mut store = ArrayStore::default();
+        store.reserve(NodeId(42));
+        store.reserve(NodeId(7));
+        store.finalize();
+
+        // A reserved id starts unset, then resolves once filled.
+        assert_eq!(store.get(NodeId(42)), None);
+        assert!(store.set(NodeId(42), 13.404954, 52.520008));
+
+        let (lon, lat) = store.get(NodeId(42)).unwrap();
+        assert!((lon - 13.404954).abs() < 1e-6);
+        assert!((lat - 52.520008).abs() < 1e-6);
+    }
+
+    #[test]
+    fn array_store_ignores_unreserved_ids() {
+        let mut store = ArrayStore::default();
+        store.reserve(NodeId(1));
+        store.finalize();
+
+        // An id that was never reserved is neither stored nor found.
+        assert!(!store.set(NodeId(2), 1.0, 2.0));
+        assert_eq!(store.get(NodeId(2)), None);
+    }
+
+    #[test]
+    fn array_store_dedups_reserved_ids() {
+        let mut store = ArrayStore::default();
+        store.reserve(NodeId(5));
+        store.reserve(NodeId(5));
+        store.finalize();
+
+        assert_eq!(store.ids.len(), 1);
+        assert!(store.set(NodeId(5), 0.0, 0.0));
+    }
+}
@@ -0,0 +1,256 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use osmpbfreader::{NodeId, WayId};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+/// The kind of change block an element was found in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Create,
+    Modify,
+    Delete,
+}
+
+/// A way touched by an `.osc` diff.
+pub struct WayChange {
+    pub action: Action,
+    pub id: WayId,
+    pub nodes: Vec<NodeId>,
+    pub tags: Vec<(String, String)>,
+}
+
+/// A relation touched by an `.osc` diff.
+pub struct RelationChange {
+    pub action: Action,
+    pub id: i64,
+    pub members: Vec<WayId>,
+    pub tags: Vec<(String, String)>,
+}
+
+/// The parsed contents of an `.osc` change file.
+#[derive(Default)]
+pub struct Changes {
+    /// Coordinates of created/modified nodes, keyed by node id.
+    pub nodes: Vec<(NodeId, (f64, f64))>,
+    /// Ids of nodes dropped by the diff, so their cached coordinate can be evicted.
+    pub deleted_nodes: Vec<NodeId>,
+    pub ways: Vec<WayChange>,
+    pub relations: Vec<RelationChange>,
+}
+
+/// Read the value of an attribute from a start/empty element.
+fn attr(e: &quick_xml::events::BytesStart, key: &[u8]) -> Option<String> {
+    e.attributes().flatten().find(|a| a.key.as_ref() == key).map(|a| {
+        String::from_utf8_lossy(&a.value).into_owned()
+    })
+}
+
+/// Parse an OsmChange (`.osc`) file into a flat set of changes.
+pub fn parse(path: &Path) -> Result<Changes, Box<dyn Error>> {
+    let mut reader = Reader::from_reader(BufReader::new(File::open(path)?));
+    reader.config_mut().trim_text(true);
+
+    let mut changes = Changes::default();
+    let mut buf = Vec::new();
+
+    // The surrounding change block and the element currently being built.
+    let mut action = None;
+    let mut cur_way: Option<WayChange> = None;
+    let mut cur_rel: Option<RelationChange> = None;
+
+    loop {
+        // `.osc` mixes empty elements (`<node .../>`) with parents that carry
+        // children (`<way>...</way>`), so track whether the element self-closes.
+        let (e, self_closing) = match reader.read_event_into(&mut buf)? {
+            Event::Eof => break,
+            Event::Start(e) => (e.into_owned(), false),
+            Event::Empty(e) => (e.into_owned(), true),
+            Event::End(end) => {
+                match end.name().as_ref() {
+                    b"way" => changes.ways.extend(cur_way.take()),
+                    b"relation" => changes.relations.extend(cur_rel.take()),
+                    _ => {}
+                }
+                buf.clear();
+                continue;
+            }
+            _ => {
+                buf.clear();
+                continue;
+            }
+        };
+
+        match e.name().as_ref() {
+            b"create" => action = Some(Action::Create),
+            b"modify" => action = Some(Action::Modify),
+            b"delete" => action = Some(Action::Delete),
+
+            b"node" => {
+                let action = action.expect("node outside a change block");
+
+                if action == Action::Delete {
+                    if let Some(id) = attr(&e, b"id").and_then(|s| s.parse().ok()) {
+                        changes.deleted_nodes.push(NodeId(id));
+                    }
+                } else if let (Some(id), Some(lon), Some(lat)) = (
+                    attr(&e, b"id").and_then(|s| s.parse().ok()),
+                    attr(&e, b"lon").and_then(|s| s.parse().ok()),
+                    attr(&e, b"lat").and_then(|s| s.parse().ok()),
+                ) {
+                    changes.nodes.push((NodeId(id), (lon, lat)));
+                }
+            }
+
+            b"way" => {
+                let action = action.expect("way outside a change block");
+                let id = attr(&e, b"id").and_then(|s| s.parse().ok()).unwrap_or(0);
+                let way = WayChange {
+                    action,
+                    id: WayId(id),
+                    nodes: Vec::new(),
+                    tags: Vec::new(),
+                };
+
+                if self_closing {
+                    changes.ways.push(way);
+                } else {
+                    cur_way = Some(way);
+                }
+            }
+
+            b"relation" => {
+                let action = action.expect("relation outside a change block");
+                let id = attr(&e, b"id").and_then(|s| s.parse().ok()).unwrap_or(0);
+                let rel = RelationChange {
+                    action,
+                    id,
+                    members: Vec::new(),
+                    tags: Vec::new(),
+                };
+
+                if self_closing {
+                    changes.relations.push(rel);
+                } else {
+                    cur_rel = Some(rel);
+                }
+            }
+
+            b"member" => {
+                // Only way members contribute geometry to a city wall relation.
+                if attr(&e, b"type").as_deref() == Some("way") {
+                    if let (Some(rel), Some(id)) =
+                        (cur_rel.as_mut(), attr(&e, b"ref").and_then(|s| s.parse().ok()))
+                    {
+                        rel.members.push(WayId(id));
+                    }
+                }
+            }
+
+            b"nd" => {
+                if let (Some(way), Some(id)) =
+                    (cur_way.as_mut(), attr(&e, b"ref").and_then(|s| s.parse().ok()))
+                {
+                    way.nodes.push(NodeId(id));
+                }
+            }
+
+            b"tag" => {
+                if let (Some(k), Some(v)) = (attr(&e, b"k"), attr(&e, b"v")) {
+                    if let Some(way) = cur_way.as_mut() {
+                        way.tags.push((k, v));
+                    } else if let Some(rel) = cur_rel.as_mut() {
+                        rel.tags.push((k, v));
+                    }
+                }
+            }
+
+            _ => {}
+        }
+
+        buf.clear();
+    }
+
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse an `.osc` document from a string via a throwaway temp file.
+    fn parse_str(name: &str, xml: &str) -> Changes {
+        let path = std::env::temp_dir().join(format!("cwall_{name}.osc"));
+        std::fs::write(&path, xml).unwrap();
+        let changes = parse(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        changes
+    }
+
+    #[test]
+    fn parses_creates_modifies_and_deletes() {
+        let xml = r#"<osmChange>
+          <create>
+            <node id="1" lon="1.0" lat="2.0"/>
+            <node id="2" lon="3.0" lat="4.0"/>
+            <way id="10">
+              <nd ref="1"/>
+              <nd ref="2"/>
+              <tag k="barrier" v="city_wall"/>
+            </way>
+          </create>
+          <modify>
+            <relation id="20">
+              <member type="way" ref="10" role="outer"/>
+              <member type="node" ref="1" role=""/>
+              <tag k="type" v="multipolygon"/>
+            </relation>
+          </modify>
+          <delete>
+            <node id="3"/>
+          </delete>
+        </osmChange>"#;
+
+        let changes = parse_str("full", xml);
+
+        // Created node coordinates are kept; the deleted node is recorded apart.
+        assert_eq!(changes.nodes, vec![(NodeId(1), (1.0, 2.0)), (NodeId(2), (3.0, 4.0))]);
+        assert_eq!(changes.deleted_nodes, vec![NodeId(3)]);
+
+        // The child-bearing way carries its nd refs and tags.
+        assert_eq!(changes.ways.len(), 1);
+        let way = &changes.ways[0];
+        assert_eq!(way.id, WayId(10));
+        assert_eq!(way.action, Action::Create);
+        assert_eq!(way.nodes, vec![NodeId(1), NodeId(2)]);
+        assert_eq!(way.tags, vec![("barrier".to_string(), "city_wall".to_string())]);
+
+        // Only way members contribute; the node member is ignored.
+        assert_eq!(changes.relations.len(), 1);
+        let rel = &changes.relations[0];
+        assert_eq!(rel.id, 20);
+        assert_eq!(rel.action, Action::Modify);
+        assert_eq!(rel.members, vec![WayId(10)]);
+        assert_eq!(rel.tags, vec![("type".to_string(), "multipolygon".to_string())]);
+    }
+
+    #[test]
+    fn handles_self_closing_way_element() {
+        // A `<way .../>` with no children (e.g. a delete) must still be captured.
+        let xml = r#"<osmChange>
+          <delete>
+            <way id="11"/>
+          </delete>
+        </osmChange>"#;
+
+        let changes = parse_str("selfclose", xml);
+
+        assert_eq!(changes.ways.len(), 1);
+        assert_eq!(changes.ways[0].id, WayId(11));
+        assert_eq!(changes.ways[0].action, Action::Delete);
+        assert!(changes.ways[0].nodes.is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::f64::consts::SQRT_2;
+
+/// A square candidate cell of the pole-of-inaccessibility search.
+#[derive(Clone, Copy)]
+struct Cell {
+    x: f64,
+    y: f64,
+    /// Half the cell's side length.
+    h: f64,
+    /// Signed distance from the cell center to the polygon boundary.
+    d: f64,
+    /// The greatest distance any point in the cell could achieve.
+    max: f64,
+}
+
+impl Cell {
+    fn new(x: f64, y: f64, h: f64, polygon: &[(f64, f64)]) -> Self {
+        let d = point_to_polygon_dist(x, y, polygon);
+        Cell {
+            x,
+            y,
+            h,
+            d,
+            max: d + h * SQRT_2,
+        }
+    }
+}
+
+// Ordered by `max` so the priority queue yields the most promising cell first.
+impl PartialEq for Cell {
+    fn eq(&self, other: &Self) -> bool {
+        self.max == other.max
+    }
+}
+
+impl Eq for Cell {}
+
+impl PartialOrd for Cell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Cell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.max.total_cmp(&other.max)
+    }
+}
+
+/// Squared distance from `(px, py)` to the segment `a`–`b`.
+fn segment_dist_sq(px: f64, py: f64, (ax, ay): (f64, f64), (bx, by): (f64, f64)) -> f64 {
+    let (mut x, mut y) = (ax, ay);
+    let dx = bx - ax;
+    let dy = by - ay;
+
+    if dx != 0.0 || dy != 0.0 {
+        let t = ((px - ax) * dx + (py - ay) * dy) / (dx * dx + dy * dy);
+
+        if t > 1.0 {
+            x = bx;
+            y = by;
+        } else if t > 0.0 {
+            x += dx * t;
+            y += dy * t;
+        }
+    }
+
+    let (ex, ey) = (px - x, py - y);
+    ex * ex + ey * ey
+}
+
+/// Signed distance from `(x, y)` to the polygon boundary, positive inside.
+fn point_to_polygon_dist(x: f64, y: f64, polygon: &[(f64, f64)]) -> f64 {
+    let mut inside = false;
+    let mut min_dist_sq = f64::INFINITY;
+    let n = polygon.len();
+    let mut j = n - 1;
+
+    for i in 0..n {
+        let (ax, ay) = polygon[i];
+        let (bx, by) = polygon[j];
+
+        if ((ay > y) != (by > y)) && (x < (bx - ax) * (y - ay) / (by - ay) + ax) {
+            inside = !inside;
+        }
+
+        min_dist_sq = min_dist_sq.min(segment_dist_sq(x, y, polygon[i], polygon[j]));
+        j = i;
+    }
+
+    let dist = min_dist_sq.sqrt();
+    if inside {
+        dist
+    } else {
+        -dist
+    }
+}
+
+/// The area-weighted centroid as a zero-size cell (a decent initial guess).
+fn centroid_cell(polygon: &[(f64, f64)]) -> Cell {
+    let mut area = 0.0;
+    let mut x = 0.0;
+    let mut y = 0.0;
+    let n = polygon.len();
+    let mut j = n - 1;
+
+    for i in 0..n {
+        let (ax, ay) = polygon[i];
+        let (bx, by) = polygon[j];
+        let f = ax * by - bx * ay;
+        x += (ax + bx) * f;
+        y += (ay + by) * f;
+        area += f * 3.0;
+        j = i;
+    }
+
+    if area == 0.0 {
+        return Cell::new(polygon[0].0, polygon[0].1, 0.0, polygon);
+    }
+
+    Cell::new(x / area, y / area, 0.0, polygon)
+}
+
+/// Compute the pole of inaccessibility — the interior point farthest from the
+/// boundary — of a polygon ring, to `precision`. This is the
+/// [polylabel](https://github.com/mapbox/polylabel) algorithm.
+pub fn polylabel(polygon: &[(f64, f64)], precision: f64) -> (f64, f64) {
+    let min_x = polygon.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+    let min_y = polygon.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let max_x = polygon.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+    let max_y = polygon.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+
+    let width = max_x - min_x;
+    let height = max_y - min_y;
+    let cell_size = width.min(height);
+
+    if cell_size == 0.0 {
+        return (min_x, min_y);
+    }
+
+    let h = cell_size / 2.0;
+
+    // Seed the queue with a grid of cells covering the bounding box.
+    let mut queue = BinaryHeap::new();
+    let mut x = min_x;
+    while x < max_x {
+        let mut y = min_y;
+        while y < max_y {
+            queue.push(Cell::new(x + h, y + h, h, polygon));
+            y += cell_size;
+        }
+        x += cell_size;
+    }
+
+    // Start from the centroid, but also try the bounding box center.
+    let mut best = centroid_cell(polygon);
+    let bbox = Cell::new(min_x + width / 2.0, min_y + height / 2.0, 0.0, polygon);
+    if bbox.d > best.d {
+        best = bbox;
+    }
+
+    while let Some(cell) = queue.pop() {
+        if cell.d > best.d {
+            best = cell;
+        }
+
+        // Stop splitting once the cell cannot beat the best by more than precision.
+        if cell.max - best.d <= precision {
+            continue;
+        }
+
+        let h = cell.h / 2.0;
+        queue.push(Cell::new(cell.x - h, cell.y - h, h, polygon));
+        queue.push(Cell::new(cell.x + h, cell.y - h, h, polygon));
+        queue.push(Cell::new(cell.x - h, cell.y + h, h, polygon));
+        queue.push(Cell::new(cell.x + h, cell.y + h, h, polygon));
+    }
+
+    (best.x, best.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: [(f64, f64); 4] = [(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)];
+
+    #[test]
+    fn point_dist_is_signed() {
+        // Positive inside, negative outside, magnitude is the boundary distance.
+        assert!((point_to_polygon_dist(2.0, 2.0, &SQUARE) - 2.0).abs() < 1e-9);
+        assert!((point_to_polygon_dist(6.0, 2.0, &SQUARE) + 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn segment_dist_handles_projection_and_endpoints() {
+        // Perpendicular foot falls inside the segment.
+        assert!((segment_dist_sq(0.5, 1.0, (0.0, 0.0), (1.0, 0.0)) - 1.0).abs() < 1e-9);
+        // Beyond an endpoint clamps to that endpoint.
+        assert!((segment_dist_sq(2.0, 0.0, (0.0, 0.0), (1.0, 0.0)) - 1.0).abs() < 1e-9);
+        // A degenerate (zero-length) segment is just point distance.
+        assert!((segment_dist_sq(3.0, 4.0, (0.0, 0.0), (0.0, 0.0)) - 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn label_of_square_is_its_center() {
+        let (x, y) = polylabel(&SQUARE, 1e-3);
+        assert!((x - 2.0).abs() < 1e-2, "x = {x}");
+        assert!((y - 2.0).abs() < 1e-2, "y = {y}");
+    }
+
+    #[test]
+    fn degenerate_polygon_terminates_at_its_point() {
+        // A zero-area ring has no interior; the search must still return.
+        let point = [(1.0, 1.0), (1.0, 1.0), (1.0, 1.0)];
+        assert_eq!(polylabel(&point, 1e-3), (1.0, 1.0));
+    }
+}
@@ -0,0 +1,537 @@
+use std::error::Error;
+use std::fs::File;
+use std::path::PathBuf;
+
+use mysql::{prelude::*, Pool, PooledConn, Statement, Value as MyValue};
+use osmpbfreader::NodeId;
+use serde_json::{json, Map, Value};
+
+use crate::geometry::Geometry;
+
+/// A destination for the assembled city walls.
+pub trait WallSink {
+    /// Insert one wall keyed by its stable OSM id, with name, extra copied tag
+    /// values, geometry and an optional label point (the pole of inaccessibility
+    /// of a ring). `extra` aligns with the sink's configured extra tag keys.
+    fn write(
+        &mut self,
+        osm_id: i64,
+        name: Option<&str>,
+        extra: &[Option<&str>],
+        geometry: &Geometry,
+        label: Option<(f64, f64)>,
+    ) -> Result<(), Box<dyn Error>>;
+
+    /// Insert or replace the row for `osm_id` (used by `--append`). Sinks that
+    /// cannot update in place reject this.
+    fn upsert(
+        &mut self,
+        _osm_id: i64,
+        _name: Option<&str>,
+        _extra: &[Option<&str>],
+        _geometry: &Geometry,
+        _label: Option<(f64, f64)>,
+    ) -> Result<(), Box<dyn Error>> {
+        Err("this output backend does not support incremental updates".into())
+    }
+
+    /// Remove the row for `osm_id` (used by `--append` for deletes/untags).
+    fn delete(&mut self, _osm_id: i64) -> Result<(), Box<dyn Error>> {
+        Err("this output backend does not support incremental updates".into())
+    }
+
+    /// Record a node coordinate in the geometry cache that backs `--append`.
+    /// Backends without incremental support simply drop it.
+    fn cache_node(&mut self, _id: i64, _lon: f64, _lat: f64) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// Record a way's node-id list in the geometry cache that backs `--append`.
+    fn cache_way(&mut self, _way_id: i64, _nodes: &[i64]) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// Record a relation's member way ids and whether it is a multipolygon in
+    /// the geometry cache.
+    fn cache_relation(
+        &mut self,
+        _rel_id: i64,
+        _member_ways: &[i64],
+        _multipolygon: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// Map the fragment ways of a stitched standalone component to the
+    /// representative `osm_id` its merged row is stored under.
+    fn cache_standalone(&mut self, _rep_id: i64, _member_ways: &[i64]) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// Record the name and extra tag values of an output feature so `--append`
+    /// can rewrite its row without re-reading the original tags.
+    fn cache_feature_tags(
+        &mut self,
+        _osm_id: i64,
+        _name: Option<&str>,
+        _extra: &[Option<&str>],
+    ) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// Flush any buffered state (commit the transaction, write the file, ...).
+    fn finish(self: Box<Self>) -> Result<(), Box<dyn Error>>;
+}
+
+/// Sink backed by the `cwalls` MySQL table.
+pub struct MySqlSink {
+    conn: PooledConn,
+    insert_stmt: Statement,
+    upsert_stmt: Statement,
+    delete_stmt: Statement,
+    // Side tables that let `--append` re-resolve geometry from a diff alone.
+    node_cache_stmt: Statement,
+    node_evict_stmt: Statement,
+    way_cache_stmt: Statement,
+    way_evict_stmt: Statement,
+    rel_evict_stmt: Statement,
+    rel_cache_stmt: Statement,
+    rel_meta_stmt: Statement,
+    rel_meta_evict_stmt: Statement,
+    standalone_evict_stmt: Statement,
+    standalone_cache_stmt: Statement,
+    tags_cache_stmt: Statement,
+    tags_evict_stmt: Statement,
+}
+
+impl MySqlSink {
+    /// Open a connection and recreate the table for a full import.
+    pub fn new(conn_str: &str, extra_cols: &[String]) -> Result<Self, Box<dyn Error>> {
+        let pool = Pool::new(conn_str)?;
+        let mut conn = pool.get_conn()?;
+
+        // Drop the existing table and recreate it, with one text column per copied tag.
+        conn.query_drop("DROP TABLE IF EXISTS cwalls")?;
+
+        let mut defs = vec![
+            "id INT NOT NULL AUTO_INCREMENT".to_string(),
+            "osm_id BIGINT NOT NULL".to_string(),
+            "name TEXT".to_string(),
+        ];
+        defs.extend(extra_cols.iter().map(|c| format!("`{c}` TEXT")));
+        defs.push("geo GEOMETRY NOT NULL".to_string());
+        defs.push("label POINT".to_string());
+        defs.push("PRIMARY KEY (id)".to_string());
+        defs.push("UNIQUE KEY osm_id (osm_id)".to_string());
+
+        conn.query_drop(format!("CREATE TABLE cwalls ({})", defs.join(", ")))?;
+
+        // Recreate the geometry cache so `--append` can re-resolve node
+        // coordinates and relation members that a later diff leaves untouched.
+        conn.query_drop("DROP TABLE IF EXISTS cwall_nodes")?;
+        conn.query_drop(
+            "CREATE TABLE cwall_nodes \
+             (node_id BIGINT NOT NULL, lon DOUBLE NOT NULL, lat DOUBLE NOT NULL, \
+              PRIMARY KEY (node_id))",
+        )?;
+
+        conn.query_drop("DROP TABLE IF EXISTS cwall_ways")?;
+        conn.query_drop(
+            "CREATE TABLE cwall_ways \
+             (way_id BIGINT NOT NULL, nodes JSON NOT NULL, PRIMARY KEY (way_id))",
+        )?;
+
+        conn.query_drop("DROP TABLE IF EXISTS cwall_rel_members")?;
+        conn.query_drop(
+            "CREATE TABLE cwall_rel_members \
+             (rel_id BIGINT NOT NULL, pos INT NOT NULL, way_id BIGINT NOT NULL, \
+              PRIMARY KEY (rel_id, pos))",
+        )?;
+
+        conn.query_drop("DROP TABLE IF EXISTS cwall_relations")?;
+        conn.query_drop(
+            "CREATE TABLE cwall_relations \
+             (rel_id BIGINT NOT NULL, multipolygon TINYINT NOT NULL, PRIMARY KEY (rel_id))",
+        )?;
+
+        conn.query_drop("DROP TABLE IF EXISTS cwall_standalone")?;
+        conn.query_drop(
+            "CREATE TABLE cwall_standalone \
+             (way_id BIGINT NOT NULL, rep_id BIGINT NOT NULL, \
+              PRIMARY KEY (way_id), KEY rep_id (rep_id))",
+        )?;
+
+        conn.query_drop("DROP TABLE IF EXISTS cwall_feature_tags")?;
+        conn.query_drop(
+            "CREATE TABLE cwall_feature_tags \
+             (osm_id BIGINT NOT NULL, name TEXT, extra JSON NOT NULL, PRIMARY KEY (osm_id))",
+        )?;
+
+        Self::prepare(conn, extra_cols)
+    }
+
+    /// Open a connection against the existing table for incremental updates.
+    pub fn open(conn_str: &str, extra_cols: &[String]) -> Result<Self, Box<dyn Error>> {
+        let pool = Pool::new(conn_str)?;
+        let conn = pool.get_conn()?;
+        Self::prepare(conn, extra_cols)
+    }
+
+    /// Prepare the insert/upsert/delete statements and open a transaction.
+    fn prepare(mut conn: PooledConn, extra_cols: &[String]) -> Result<Self, Box<dyn Error>> {
+        // The column list shared by insert and upsert.
+        let mut cols = vec!["osm_id".to_string(), "name".to_string()];
+        cols.extend(extra_cols.iter().map(|c| format!("`{c}`")));
+
+        let marks = vec!["?"; cols.len()].join(", ");
+        let body = format!(
+            "INTO cwalls ({}, geo, label) \
+             VALUES ({marks}, ST_GeomFromText(?), ST_GeomFromText(?))",
+            cols.join(", ")
+        );
+
+        // The `ON DUPLICATE KEY UPDATE` clause refreshes every non-key column.
+        let mut updates = vec!["name = VALUES(name)".to_string()];
+        updates.extend(extra_cols.iter().map(|c| format!("`{c}` = VALUES(`{c}`)")));
+        updates.push("geo = VALUES(geo)".to_string());
+        updates.push("label = VALUES(label)".to_string());
+
+        let insert_stmt = conn.prep(format!("INSERT {body}"))?;
+        let upsert_stmt =
+            conn.prep(format!("INSERT {body} ON DUPLICATE KEY UPDATE {}", updates.join(", ")))?;
+        let delete_stmt = conn.prep("DELETE FROM cwalls WHERE osm_id = ?")?;
+
+        // Geometry cache statements, shared by the full import (which fills the
+        // cache) and `--append` (which refreshes it as it applies a diff).
+        let node_cache_stmt = conn.prep(
+            "INSERT INTO cwall_nodes (node_id, lon, lat) VALUES (?, ?, ?) \
+             ON DUPLICATE KEY UPDATE lon = VALUES(lon), lat = VALUES(lat)",
+        )?;
+        let node_evict_stmt = conn.prep("DELETE FROM cwall_nodes WHERE node_id = ?")?;
+        let way_cache_stmt = conn.prep(
+            "INSERT INTO cwall_ways (way_id, nodes) VALUES (?, ?) \
+             ON DUPLICATE KEY UPDATE nodes = VALUES(nodes)",
+        )?;
+        let way_evict_stmt = conn.prep("DELETE FROM cwall_ways WHERE way_id = ?")?;
+        let rel_evict_stmt = conn.prep("DELETE FROM cwall_rel_members WHERE rel_id = ?")?;
+        let rel_cache_stmt =
+            conn.prep("INSERT INTO cwall_rel_members (rel_id, pos, way_id) VALUES (?, ?, ?)")?;
+        let rel_meta_stmt = conn.prep(
+            "INSERT INTO cwall_relations (rel_id, multipolygon) VALUES (?, ?) \
+             ON DUPLICATE KEY UPDATE multipolygon = VALUES(multipolygon)",
+        )?;
+        let rel_meta_evict_stmt = conn.prep("DELETE FROM cwall_relations WHERE rel_id = ?")?;
+        let standalone_evict_stmt = conn.prep("DELETE FROM cwall_standalone WHERE rep_id = ?")?;
+        let standalone_cache_stmt =
+            conn.prep("INSERT INTO cwall_standalone (way_id, rep_id) VALUES (?, ?)")?;
+        let tags_cache_stmt = conn.prep(
+            "INSERT INTO cwall_feature_tags (osm_id, name, extra) VALUES (?, ?, ?) \
+             ON DUPLICATE KEY UPDATE name = VALUES(name), extra = VALUES(extra)",
+        )?;
+        let tags_evict_stmt = conn.prep("DELETE FROM cwall_feature_tags WHERE osm_id = ?")?;
+
+        // Collect the whole run into a single transaction.
+        conn.query_drop("START TRANSACTION")?;
+
+        Ok(Self {
+            conn,
+            insert_stmt,
+            upsert_stmt,
+            delete_stmt,
+            node_cache_stmt,
+            node_evict_stmt,
+            way_cache_stmt,
+            way_evict_stmt,
+            rel_evict_stmt,
+            rel_cache_stmt,
+            rel_meta_stmt,
+            rel_meta_evict_stmt,
+            standalone_evict_stmt,
+            standalone_cache_stmt,
+            tags_cache_stmt,
+            tags_evict_stmt,
+        })
+    }
+
+    /// Assemble the positional values shared by insert and upsert.
+    fn row_values(
+        osm_id: i64,
+        name: Option<&str>,
+        extra: &[Option<&str>],
+        geometry: &Geometry,
+        label: Option<(f64, f64)>,
+    ) -> Vec<MyValue> {
+        let mut values = vec![MyValue::from(osm_id), MyValue::from(name)];
+        values.extend(extra.iter().map(|v| MyValue::from(*v)));
+        values.push(MyValue::from(geometry.to_wkt()));
+        values.push(MyValue::from(
+            label.map(|(lon, lat)| format!("Point({lon:.7} {lat:.7})")),
+        ));
+        values
+    }
+
+    /// Evict a node coordinate from the cache (the node was deleted upstream).
+    pub fn evict_node(&mut self, id: i64) -> Result<(), Box<dyn Error>> {
+        self.conn.exec_drop(&self.node_evict_stmt, (id,))?;
+        Ok(())
+    }
+
+    /// Evict a way's node-list from the cache (the way was deleted or dropped).
+    pub fn evict_way(&mut self, way_id: i64) -> Result<(), Box<dyn Error>> {
+        self.conn.exec_drop(&self.way_evict_stmt, (way_id,))?;
+        Ok(())
+    }
+
+    /// Evict a relation's member list and metadata from the cache.
+    pub fn evict_relation(&mut self, rel_id: i64) -> Result<(), Box<dyn Error>> {
+        self.conn.exec_drop(&self.rel_evict_stmt, (rel_id,))?;
+        self.conn.exec_drop(&self.rel_meta_evict_stmt, (rel_id,))?;
+        Ok(())
+    }
+
+    /// Whether a cached relation was tagged `type=multipolygon`.
+    pub fn relation_multipolygon(&mut self, rel_id: i64) -> Result<bool, Box<dyn Error>> {
+        let flag: Option<i64> = self
+            .conn
+            .exec_first("SELECT multipolygon FROM cwall_relations WHERE rel_id = ?", (rel_id,))?;
+        Ok(flag.unwrap_or(0) != 0)
+    }
+
+    /// Look up a cached node coordinate, or `None` if it was never imported.
+    pub fn node_coord(&mut self, id: NodeId) -> Result<Option<(f64, f64)>, Box<dyn Error>> {
+        let row: Option<(f64, f64)> = self
+            .conn
+            .exec_first("SELECT lon, lat FROM cwall_nodes WHERE node_id = ?", (id.0,))?;
+        Ok(row)
+    }
+
+    /// Look up a cached way's node-id list, or `None` if the way is unknown.
+    pub fn way_nodes(&mut self, way_id: i64) -> Result<Option<Vec<NodeId>>, Box<dyn Error>> {
+        let row: Option<String> = self
+            .conn
+            .exec_first("SELECT nodes FROM cwall_ways WHERE way_id = ?", (way_id,))?;
+
+        row.map(|json| {
+            let ids: Vec<i64> = serde_json::from_str(&json)?;
+            Ok(ids.into_iter().map(NodeId).collect())
+        })
+        .transpose()
+    }
+
+    /// Look up the member way ids cached for a relation, in member order.
+    pub fn relation_member_ways(&mut self, rel_id: i64) -> Result<Vec<i64>, Box<dyn Error>> {
+        let ids: Vec<i64> = self.conn.exec(
+            "SELECT way_id FROM cwall_rel_members WHERE rel_id = ? ORDER BY pos",
+            (rel_id,),
+        )?;
+        Ok(ids)
+    }
+
+    /// Evict a feature's cached name/extra tags.
+    pub fn evict_feature_tags(&mut self, osm_id: i64) -> Result<(), Box<dyn Error>> {
+        self.conn.exec_drop(&self.tags_evict_stmt, (osm_id,))?;
+        Ok(())
+    }
+
+    /// The representative `osm_id` of the standalone component a fragment belongs
+    /// to, or `None` if the way is not a known standalone fragment.
+    pub fn standalone_rep(&mut self, way_id: i64) -> Result<Option<i64>, Box<dyn Error>> {
+        let rep = self
+            .conn
+            .exec_first("SELECT rep_id FROM cwall_standalone WHERE way_id = ?", (way_id,))?;
+        Ok(rep)
+    }
+
+    /// Drop the fragment mapping of a standalone component.
+    pub fn evict_standalone(&mut self, rep_id: i64) -> Result<(), Box<dyn Error>> {
+        self.conn.exec_drop(&self.standalone_evict_stmt, (rep_id,))?;
+        Ok(())
+    }
+
+    /// The fragment way ids of a standalone component.
+    pub fn standalone_members(&mut self, rep_id: i64) -> Result<Vec<i64>, Box<dyn Error>> {
+        let ids: Vec<i64> = self
+            .conn
+            .exec("SELECT way_id FROM cwall_standalone WHERE rep_id = ?", (rep_id,))?;
+        Ok(ids)
+    }
+
+    /// Every cached way that references `node_id`, via the stored node-lists.
+    pub fn ways_with_node(&mut self, node_id: i64) -> Result<Vec<i64>, Box<dyn Error>> {
+        let ids: Vec<i64> = self.conn.exec(
+            "SELECT way_id FROM cwall_ways WHERE JSON_CONTAINS(nodes, CAST(? AS JSON))",
+            (node_id,),
+        )?;
+        Ok(ids)
+    }
+
+    /// Every relation that lists `way_id` as a member.
+    pub fn relations_with_member(&mut self, way_id: i64) -> Result<Vec<i64>, Box<dyn Error>> {
+        let ids: Vec<i64> = self.conn.exec(
+            "SELECT DISTINCT rel_id FROM cwall_rel_members WHERE way_id = ?",
+            (way_id,),
+        )?;
+        Ok(ids)
+    }
+
+    /// A feature's cached name and extra tag values, aligned with the configured
+    /// copy tags, or `None` if it was never written.
+    pub fn feature_tags(
+        &mut self,
+        osm_id: i64,
+    ) -> Result<Option<(Option<String>, Vec<Option<String>>)>, Box<dyn Error>> {
+        let row: Option<(Option<String>, String)> = self
+            .conn
+            .exec_first("SELECT name, extra FROM cwall_feature_tags WHERE osm_id = ?", (osm_id,))?;
+
+        row.map(|(name, extra)| Ok((name, serde_json::from_str(&extra)?)))
+            .transpose()
+    }
+}
+
+impl WallSink for MySqlSink {
+    fn write(
+        &mut self,
+        osm_id: i64,
+        name: Option<&str>,
+        extra: &[Option<&str>],
+        geometry: &Geometry,
+        label: Option<(f64, f64)>,
+    ) -> Result<(), Box<dyn Error>> {
+        let values = Self::row_values(osm_id, name, extra, geometry, label);
+        self.conn.exec_drop(&self.insert_stmt, values)?;
+        Ok(())
+    }
+
+    fn upsert(
+        &mut self,
+        osm_id: i64,
+        name: Option<&str>,
+        extra: &[Option<&str>],
+        geometry: &Geometry,
+        label: Option<(f64, f64)>,
+    ) -> Result<(), Box<dyn Error>> {
+        let values = Self::row_values(osm_id, name, extra, geometry, label);
+        self.conn.exec_drop(&self.upsert_stmt, values)?;
+        Ok(())
+    }
+
+    fn delete(&mut self, osm_id: i64) -> Result<(), Box<dyn Error>> {
+        self.conn.exec_drop(&self.delete_stmt, (osm_id,))?;
+        Ok(())
+    }
+
+    fn cache_node(&mut self, id: i64, lon: f64, lat: f64) -> Result<(), Box<dyn Error>> {
+        self.conn.exec_drop(&self.node_cache_stmt, (id, lon, lat))?;
+        Ok(())
+    }
+
+    fn cache_way(&mut self, way_id: i64, nodes: &[i64]) -> Result<(), Box<dyn Error>> {
+        let json = serde_json::to_string(nodes)?;
+        self.conn.exec_drop(&self.way_cache_stmt, (way_id, json))?;
+        Ok(())
+    }
+
+    fn cache_relation(
+        &mut self,
+        rel_id: i64,
+        member_ways: &[i64],
+        multipolygon: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        self.conn.exec_drop(&self.rel_evict_stmt, (rel_id,))?;
+
+        for (pos, &way_id) in member_ways.iter().enumerate() {
+            self.conn
+                .exec_drop(&self.rel_cache_stmt, (rel_id, pos as i64, way_id))?;
+        }
+
+        self.conn
+            .exec_drop(&self.rel_meta_stmt, (rel_id, i64::from(multipolygon)))?;
+        Ok(())
+    }
+
+    fn cache_standalone(&mut self, rep_id: i64, member_ways: &[i64]) -> Result<(), Box<dyn Error>> {
+        self.conn.exec_drop(&self.standalone_evict_stmt, (rep_id,))?;
+
+        for &way_id in member_ways {
+            self.conn.exec_drop(&self.standalone_cache_stmt, (way_id, rep_id))?;
+        }
+
+        Ok(())
+    }
+
+    fn cache_feature_tags(
+        &mut self,
+        osm_id: i64,
+        name: Option<&str>,
+        extra: &[Option<&str>],
+    ) -> Result<(), Box<dyn Error>> {
+        let extra_json = serde_json::to_string(extra)?;
+        self.conn
+            .exec_drop(&self.tags_cache_stmt, (osm_id, name, extra_json))?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        self.conn.query_drop("COMMIT")?;
+        Ok(())
+    }
+}
+
+/// Sink that collects the walls into a GeoJSON `FeatureCollection` file.
+pub struct GeoJsonSink {
+    path: PathBuf,
+    extra_keys: Vec<String>,
+    features: Vec<Value>,
+}
+
+impl GeoJsonSink {
+    pub fn new(path: impl Into<PathBuf>, extra_keys: Vec<String>) -> Self {
+        Self {
+            path: path.into(),
+            extra_keys,
+            features: Vec::new(),
+        }
+    }
+}
+
+impl WallSink for GeoJsonSink {
+    fn write(
+        &mut self,
+        osm_id: i64,
+        name: Option<&str>,
+        extra: &[Option<&str>],
+        geometry: &Geometry,
+        label: Option<(f64, f64)>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut properties = Map::new();
+        properties.insert("osm_id".to_string(), json!(osm_id));
+        properties.insert("name".to_string(), json!(name));
+
+        for (key, value) in self.extra_keys.iter().zip(extra) {
+            properties.insert(key.clone(), json!(value));
+        }
+
+        if let Some((lon, lat)) = label {
+            properties.insert("label".to_string(), json!([lon, lat]));
+        }
+
+        self.features.push(json!({
+            "type": "Feature",
+            "properties": properties,
+            "geometry": geometry.to_geojson(),
+        }));
+
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        let collection = json!({
+            "type": "FeatureCollection",
+            "features": self.features,
+        });
+
+        serde_json::to_writer_pretty(File::create(&self.path)?, &collection)?;
+        Ok(())
+    }
+}